@@ -1,12 +1,15 @@
 #![recursion_limit = "1024"]
 use failure::Error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use stdweb::web::{window, IHistory, ILocation};
+use stdweb::Value;
 use typed_html::dom::Node;
-use typed_html::elements::{input, tr};
+use typed_html::elements::{input, textarea, tr};
 use typed_html::output::yew::Yew;
 use typed_html::{html, text};
 use yew::format::json::Json;
 use yew::format::nothing::Nothing;
+use yew::format::Binary;
 use yew::services::fetch::{FetchTask, Request, Response};
 use yew::services::{ConsoleService, FetchService};
 use yew::{ChangeData, Component, ComponentLink, Html, Renderable, ShouldRender};
@@ -17,7 +20,23 @@ pub struct Model {
     fetch_service: FetchService,
     ft: Option<FetchTask>,
     link: ComponentLink<Model>,
-    table: Vec<OrbitTemplate>,
+    results: SearchState,
+    search_error: Option<String>,
+    page: i32,
+    per_page: i32,
+    num_results: i32,
+    editing: Option<String>,
+    draft: Option<OrbitTemplate>,
+    edit_error: Option<String>,
+    save_ft: Option<(String, FetchTask)>,
+}
+
+#[derive(Debug)]
+enum SearchState {
+    Idle,
+    Loading,
+    Loaded(Vec<OrbitTemplate>),
+    Failed(String),
 }
 #[derive(Deserialize, Debug)]
 pub struct QueryResult {
@@ -27,7 +46,7 @@ pub struct QueryResult {
     num_results: i32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct OrbitTemplate {
     id: String,
     matter: String,
@@ -42,26 +61,78 @@ pub struct OrbitTemplate {
 }
 
 impl Model {
+    fn full_query_string(&self) -> String {
+        format!(
+            "{}&page={}&per_page={}",
+            self.query.to_query_string(),
+            self.page,
+            self.per_page
+        )
+    }
+
     fn search(&mut self) -> FetchTask {
+        let uri = format!("http://foo.bar:4848/templates?{}", self.full_query_string());
+        // Accept-Encoding is a forbidden header name under the Fetch spec, so the
+        // browser manages it (and advertises gzip/br/zstd on its own); the body
+        // handed to the callback below is already decoded by the browser.
         let request = Request::builder()
             .method("GET")
-            .uri("http://foo.bar:4848/templates")
+            .uri(uri)
             .body(Nothing)
             .unwrap();
 
+        let callback = self.link.send_back(move |response: Response<Binary>| {
+            let (meta, body) = response.into_parts();
+            if !meta.status.is_success() {
+                return Some(Msg::SearchError(format!(
+                    "request failed with status {}",
+                    meta.status
+                )));
+            }
+            let body = match body {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    return Some(Msg::SearchError(format!(
+                        "failed to fetch response body: {}",
+                        err
+                    )))
+                }
+            };
+            // The user agent already transparently decodes Content-Encoding
+            // (gzip/br/zstd) before handing us the body, so there's nothing
+            // left to decompress here.
+            match serde_json::from_slice::<QueryResult>(&body) {
+                Ok(result) => Some(Msg::SearchResults(result)),
+                Err(err) => Some(Msg::SearchError(format!(
+                    "failed to parse response: {}",
+                    err
+                ))),
+            }
+        });
+        self.fetch_service.fetch_binary(request, callback)
+    }
+
+    fn save_template(&mut self, id: &str, template: &OrbitTemplate) -> FetchTask {
+        let uri = format!("http://foo.bar:4848/templates/{}", urlencode(id));
+        let request = Request::builder()
+            .method("PUT")
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(Json(template))
+            .unwrap();
+
         let callback = self.link.send_back(
-            move |response: Response<Json<Result<QueryResult, Error>>>| {
+            move |response: Response<Json<Result<OrbitTemplate, Error>>>| {
                 let (meta, Json(result)) = response.into_parts();
                 if !meta.status.is_success() {
-                    // self.console.log(&format!("non-ok meta: {:?}", meta));
-                    return None;
+                    return Some(Msg::SaveError(format!(
+                        "save failed with status {}",
+                        meta.status
+                    )));
                 }
                 match result {
-                    Ok(body) => Some(Msg::SearchResults(body)),
-                    Err(_) => {
-                        // self.console.log(&format!("error fetching: {:?}", err));
-                        None
-                    }
+                    Ok(updated) => Some(Msg::TemplateSaved(updated)),
+                    Err(err) => Some(Msg::SaveError(format!("failed to parse response: {}", err))),
                 }
             },
         );
@@ -88,6 +159,105 @@ impl Query {
             FilterUpdate::MimeType(s) => self.mime_type = s,
         }
     }
+
+    fn is_empty(&self) -> bool {
+        self.matter.is_none()
+            && self.language.is_none()
+            && self.brand.is_none()
+            && self.medium.is_none()
+            && self.mime_type.is_none()
+    }
+
+    fn to_query_string(&self) -> String {
+        let mut pairs = Vec::new();
+        if let Some(ref s) = self.matter {
+            pairs.push(format!("matter={}", urlencode(s)));
+        }
+        if let Some(ref s) = self.language {
+            pairs.push(format!("language={}", urlencode(s)));
+        }
+        if let Some(ref s) = self.brand {
+            pairs.push(format!("brand={}", urlencode(s)));
+        }
+        if let Some(ref s) = self.medium {
+            pairs.push(format!("medium={}", urlencode(s)));
+        }
+        if let Some(ref s) = self.mime_type {
+            pairs.push(format!("mime_type={}", urlencode(s)));
+        }
+        pairs.join("&")
+    }
+}
+
+fn urlencode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn urldecode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                    out.push(byte);
+                    i += 3;
+                } else {
+                    out.push(bytes[i]);
+                    i += 1;
+                }
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn parse_location_query(search: &str) -> (Query, Option<i32>) {
+    let mut query = Query::default();
+    let mut page = None;
+    for pair in search.trim_start_matches('?').split('&') {
+        if pair.is_empty() {
+            continue;
+        }
+        let mut parts = pair.splitn(2, '=');
+        let key = parts.next().unwrap_or("");
+        let value = urldecode(parts.next().unwrap_or(""));
+        match key {
+            "matter" => query.matter = none_if_empty(value),
+            "language" => query.language = none_if_empty(value),
+            "brand" => query.brand = none_if_empty(value),
+            "medium" => query.medium = none_if_empty(value),
+            "mime_type" => query.mime_type = none_if_empty(value),
+            "page" => page = value.parse().ok(),
+            _ => {}
+        }
+    }
+    (query, page)
+}
+
+fn sync_query_to_location(query_string: &str) {
+    let url = format!("?{}", query_string);
+    window().history().push_state(Value::Null, "", Some(&url));
 }
 
 #[derive(Debug)]
@@ -95,8 +265,32 @@ pub enum Msg {
     QueryFilterUpdate(FilterUpdate),
     SearchAction,
     SearchResults(QueryResult),
+    SearchError(String),
+    GoToPage(i32),
+    EditTemplate(String),
+    UpdateField {
+        id: String,
+        field: TemplateField,
+        value: String,
+    },
+    SaveTemplate(String),
+    TemplateSaved(OrbitTemplate),
+    SaveError(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TemplateField {
+    Subject,
+    Body,
+    Brand,
+    Language,
+    Medium,
+    MimeType,
 }
 
+const EMPTY_SEARCH_MESSAGE: &'static str = "Please enter a search term";
+const DEFAULT_PER_PAGE: i32 = 20;
+
 impl From<FilterUpdate> for Msg {
     fn from(filter: FilterUpdate) -> Self {
         Msg::QueryFilterUpdate(filter)
@@ -117,14 +311,35 @@ impl Component for Model {
     type Properties = ();
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Model {
-            query: Query::default(),
+        let search = window()
+            .location()
+            .map(|location| location.search().unwrap_or_default())
+            .unwrap_or_default();
+        let (query, page) = parse_location_query(&search);
+
+        let mut model = Model {
+            query,
             console: ConsoleService::new(),
             fetch_service: FetchService::new(),
             ft: None,
             link,
-            table: Vec::new(),
+            results: SearchState::Idle,
+            search_error: None,
+            page: page.unwrap_or(1),
+            per_page: DEFAULT_PER_PAGE,
+            num_results: 0,
+            editing: None,
+            draft: None,
+            edit_error: None,
+            save_ft: None,
+        };
+
+        if !model.query.is_empty() {
+            model.results = SearchState::Loading;
+            model.ft = Some(model.search());
         }
+
+        model
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
@@ -133,10 +348,80 @@ impl Component for Model {
 
             match msg {
                 Msg::QueryFilterUpdate(filter) => self.query.update(filter),
-                Msg::SearchAction => self.ft = Some(self.search()),
+                Msg::SearchAction => {
+                    if self.query.is_empty() {
+                        self.search_error = Some(EMPTY_SEARCH_MESSAGE.to_owned());
+                    } else {
+                        self.search_error = None;
+                        self.page = 1;
+                        sync_query_to_location(&self.full_query_string());
+                        self.results = SearchState::Loading;
+                        self.ft = Some(self.search());
+                    }
+                }
                 Msg::SearchResults(result) => {
                     self.console.log(&format!("got result: {:?}", result));
-                    self.table = result.objects
+                    self.page = result.page;
+                    self.per_page = result.per_page;
+                    self.num_results = result.num_results;
+                    self.results = SearchState::Loaded(result.objects);
+                }
+                Msg::SearchError(message) => {
+                    self.console.log(&format!("search failed: {}", message));
+                    self.results = SearchState::Failed(message);
+                }
+                Msg::GoToPage(page) => {
+                    self.page = page;
+                    sync_query_to_location(&self.full_query_string());
+                    self.results = SearchState::Loading;
+                    self.ft = Some(self.search());
+                }
+                Msg::EditTemplate(id) => {
+                    if let SearchState::Loaded(ref table) = self.results {
+                        if let Some(template) = table.iter().find(|t| t.id == id) {
+                            self.draft = Some(template.clone());
+                            self.editing = Some(id);
+                        }
+                    }
+                }
+                Msg::UpdateField { id, field, value } => {
+                    if self.editing.as_ref() == Some(&id) {
+                        if let Some(ref mut draft) = self.draft {
+                            match field {
+                                TemplateField::Subject => draft.subject = value,
+                                TemplateField::Body => draft.body = value,
+                                TemplateField::Brand => draft.brand = value,
+                                TemplateField::Language => draft.language = value,
+                                TemplateField::Medium => draft.medium = value,
+                                TemplateField::MimeType => draft.mime_type = value,
+                            }
+                        }
+                    }
+                }
+                Msg::SaveTemplate(id) => {
+                    if let Some(draft) = self.draft.clone() {
+                        if draft.id == id {
+                            self.edit_error = None;
+                            let ft = self.save_template(&id, &draft);
+                            self.save_ft = Some((id, ft));
+                        }
+                    }
+                }
+                Msg::TemplateSaved(updated) => {
+                    if let SearchState::Loaded(ref mut table) = self.results {
+                        if let Some(existing) = table.iter_mut().find(|t| t.id == updated.id) {
+                            *existing = updated.clone();
+                        }
+                    }
+                    if self.editing.as_ref() == Some(&updated.id) {
+                        self.editing = None;
+                        self.draft = None;
+                    }
+                    self.save_ft = None;
+                }
+                Msg::SaveError(message) => {
+                    self.edit_error = Some(message);
+                    self.save_ft = None;
                 }
             }
 
@@ -176,19 +461,95 @@ fn query_field(
     : Yew<Model>)
 }
 
-fn template_row(template: &OrbitTemplate) -> Box<tr<Yew<Model>>> {
+fn editable_input(id: String, field: TemplateField, value: &str) -> Box<input<Yew<Model>>> {
+    html! (
+        <input type="text" value=value onchange={move |v| {
+            match v {
+                ChangeData::Value(val) => Some(Msg::UpdateField { id: id.clone(), field, value: val }),
+                _ => None,
+            }
+        }}/>
+    : Yew<Model>)
+}
+
+fn editable_textarea(id: String, value: &str) -> Box<textarea<Yew<Model>>> {
+    html! (
+        <textarea onchange={move |v| {
+            match v {
+                ChangeData::Value(val) => Some(Msg::UpdateField { id: id.clone(), field: TemplateField::Body, value: val }),
+                _ => None,
+            }
+        }}>{ text!(value.to_owned()) }</textarea>
+    : Yew<Model>)
+}
+
+impl Model {
+    fn template_row(&self, template: &OrbitTemplate) -> Box<tr<Yew<Model>>> {
+        if self.editing.as_ref() == Some(&template.id) {
+            let draft = self.draft.as_ref().unwrap_or(template);
+            let id = template.id.clone();
+            html! (
+                <tr>
+                    <td> { editable_input(id.clone(), TemplateField::Subject, &draft.subject) } </td>
+                    <td> { editable_input(id.clone(), TemplateField::Brand, &draft.brand) } </td>
+                    <td> { editable_input(id.clone(), TemplateField::Language, &draft.language) } </td>
+                    <td> { editable_input(id.clone(), TemplateField::Medium, &draft.medium) } </td>
+                    <td> { text!(template.matter.to_owned()) } </td>
+                    <td> { editable_input(id.clone(), TemplateField::MimeType, &draft.mime_type) } </td>
+                    <td> { text!(template.created_at.to_owned()) } </td>
+                    <td> { text!(template.changed_at.to_owned()) } </td>
+                    <td> { editable_textarea(id.clone(), &draft.body) } </td>
+                    <td>
+                        <button type="button" onclick={move |_| Some(Msg::SaveTemplate(id.clone()))}>"Save"</button>
+                    </td>
+                </tr>
+            : Yew<Model>)
+        } else {
+            let id = template.id.clone();
+            html! (
+                <tr>
+                    <td> { text!(template.subject.to_owned()) } </td>
+                    <td> { text!(template.brand.to_owned()) } </td>
+                    <td> { text!(template.language.to_owned()) } </td>
+                    <td> { text!(template.medium.to_owned()) } </td>
+                    <td> { text!(template.matter.to_owned()) } </td>
+                    <td> { text!(template.mime_type.to_owned()) } </td>
+                    <td> { text!(template.created_at.to_owned()) } </td>
+                    <td> { text!(template.changed_at.to_owned()) } </td>
+                    <td> { text!(template.body.to_owned()) } </td>
+                    <td>
+                        <button type="button" onclick={move |_| Some(Msg::EditTemplate(id.clone()))}>"Edit"</button>
+                    </td>
+                </tr>
+            : Yew<Model>)
+        }
+    }
+}
+
+fn pagination_controls(
+    page: i32,
+    per_page: i32,
+    num_results: i32,
+) -> Box<typed_html::elements::div<Yew<Model>>> {
+    let last_page = if per_page > 0 {
+        ((num_results + per_page - 1) / per_page).max(1)
+    } else {
+        1
+    };
+    let prev_page = page - 1;
+    let next_page = page + 1;
+    let has_prev = page > 1;
+    let has_next = page < last_page;
     html! (
-        <tr>
-            <td> { text!(template.subject.to_owned()) } </td>
-            <td> { text!(template.brand.to_owned()) } </td>
-            <td> { text!(template.language.to_owned()) } </td>
-            <td> { text!(template.medium.to_owned()) } </td>
-            <td> { text!(template.matter.to_owned()) } </td>
-            <td> { text!(template.mime_type.to_owned()) } </td>
-            <td> { text!(template.created_at.to_owned()) } </td>
-            <td> { text!(template.changed_at.to_owned()) } </td>
-            <td> { text!(template.body.to_owned()) } </td>
-        </tr>
+        <div class="pagination">
+            <button type="button" disabled={!has_prev} onclick={move |_| {
+                if has_prev { Some(Msg::GoToPage(prev_page)) } else { None }
+            }}>"Previous"</button>
+            <span> { text!(format!("page {} of {}", page, last_page)) } </span>
+            <button type="button" disabled={!has_next} onclick={move |_| {
+                if has_next { Some(Msg::GoToPage(next_page)) } else { None }
+            }}>"Next"</button>
+        </div>
     : Yew<Model>)
 }
 
@@ -216,6 +577,19 @@ impl Renderable<Model> for Model {
                 <button type="button" onclick={|_| Some(Msg::SearchAction)}>"Search"</button>
 
                 </div>
+                { self.search_error.as_ref().map_or(html!(<div></div> : Yew<Self>), |message| html!(
+                    <div class="search-error"> { text!(message.to_owned()) } </div>
+                : Yew<Self>)) }
+                { self.edit_error.as_ref().map_or(html!(<div></div> : Yew<Self>), |message| html!(
+                    <div class="edit-error"> { text!(message.to_owned()) } </div>
+                : Yew<Self>)) }
+                { match self.results {
+                    SearchState::Loading => html!(<div class="loading">"Loading…"</div> : Yew<Self>),
+                    SearchState::Failed(ref message) => html!(
+                        <div class="error-banner"> { text!(message.to_owned()) } </div>
+                    : Yew<Self>),
+                    SearchState::Idle | SearchState::Loaded(_) => html!(<div></div> : Yew<Self>),
+                } }
                 <section class="table">
                     <table>
                         <thead>
@@ -230,10 +604,16 @@ impl Renderable<Model> for Model {
                             </tr>
                         </thead>
                         <tbody>
-                            {self.table.iter().map(template_row)}
+                            { match self.results {
+                                SearchState::Loaded(ref table) => {
+                                    table.iter().map(|t| self.template_row(t)).collect::<Vec<_>>()
+                                }
+                                _ => Vec::new(),
+                            } }
                         </tbody>
                     </table>
                 </section>
+                { pagination_controls(self.page, self.per_page, self.num_results) }
             </div>
         : Yew<Self>);
         Yew::build(doc.vnode())